@@ -0,0 +1,768 @@
+use anyhow::{bail, Context, Result};
+use hound::{SampleFormat, WavReader};
+use std::{
+    f64::consts::PI,
+    fs::{rename, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Default IR sample rate.
+pub const TARGET_SR: u32 = 48_000;
+/// Default IR length in samples.
+pub const TARGET_SAMPLES: usize = 1024;
+/// Default fade-out window length in samples.
+pub const DEFAULT_FADE_SAMPLES: usize = 32;
+
+/// Interpolation kernel used to resample a capture to the target sample rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpMode {
+    Nearest,
+    Linear,
+    Cosine,
+    Cubic,
+    /// Windowed-sinc polyphase resampling with a `±radius`-tap Hann window.
+    Polyphase { radius: usize },
+}
+
+impl InterpMode {
+    const DEFAULT_POLYPHASE_RADIUS: usize = 16;
+
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "nearest" => InterpMode::Nearest,
+            "linear" => InterpMode::Linear,
+            "cosine" => InterpMode::Cosine,
+            "cubic" => InterpMode::Cubic,
+            "polyphase" => InterpMode::Polyphase {
+                radius: Self::DEFAULT_POLYPHASE_RADIUS,
+            },
+            other => bail!(
+                "Unknown --interp mode {:?} (expected nearest|linear|cosine|cubic|polyphase)",
+                other
+            ),
+        })
+    }
+}
+
+/// Output sample format written by [`build_wav_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutBits {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl OutBits {
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "16" => OutBits::Int16,
+            "24" => OutBits::Int24,
+            "32f" => OutBits::Float32,
+            other => bail!("Unknown --out-bits {:?} (expected 16|24|32f)", other),
+        })
+    }
+}
+
+/// How a multichannel capture is folded down to the single mono IR channel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MixMode {
+    /// Equal-weight average of all channels: `sum(ch_i) / N`.
+    Average,
+    /// Per-channel coefficients, e.g. from `--mix "0.7,0.3"`.
+    Weights(Vec<f64>),
+    /// Select a single source channel by index, from `--pick K`.
+    Pick(usize),
+}
+
+impl MixMode {
+    pub fn parse_weights(s: &str) -> Result<Self> {
+        let weights = s
+            .split(',')
+            .map(|part| part.trim().parse::<f64>().with_context(|| format!("invalid --mix weight {:?}", part)))
+            .collect::<Result<Vec<f64>>>()?;
+        if weights.is_empty() {
+            bail!("--mix requires at least one weight");
+        }
+        Ok(MixMode::Weights(weights))
+    }
+}
+
+/// Level handling applied to the retained IR before re-quantization.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalizeMode {
+    /// Scale so the peak sample hits `target_dbfs` (default -0.1 dBFS).
+    Peak { target_dbfs: f64 },
+    /// Scale so the RMS level hits `target_dbfs` (default -18 dBFS).
+    Rms { target_dbfs: f64 },
+    Off,
+}
+
+impl NormalizeMode {
+    const DEFAULT_PEAK_DBFS: f64 = -0.1;
+    const DEFAULT_RMS_DBFS: f64 = -18.0;
+
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "peak" => NormalizeMode::Peak {
+                target_dbfs: Self::DEFAULT_PEAK_DBFS,
+            },
+            "rms" => NormalizeMode::Rms {
+                target_dbfs: Self::DEFAULT_RMS_DBFS,
+            },
+            "off" => NormalizeMode::Off,
+            other => bail!("Unknown --normalize mode {:?} (expected peak|rms|off)", other),
+        })
+    }
+}
+
+/// Settings controlling the full capture -> IR conversion pipeline, used by
+/// both the CLI and [`process_ir`]/[`process_samples`] for programmatic use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrConfig {
+    pub sample_rate: u32,
+    pub length: usize,
+    pub fade: usize,
+    pub interp: InterpMode,
+    pub out_bits: OutBits,
+    pub mix: MixMode,
+    pub normalize: NormalizeMode,
+}
+
+impl Default for IrConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: TARGET_SR,
+            length: TARGET_SAMPLES,
+            fade: DEFAULT_FADE_SAMPLES,
+            interp: InterpMode::Linear,
+            out_bits: OutBits::Int24,
+            mix: MixMode::Average,
+            normalize: NormalizeMode::Off,
+        }
+    }
+}
+
+/// Encoded WAV bytes plus the gain (in dB) applied by the normalization
+/// stage, so callers can report or audit it without re-deriving it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessResult {
+    pub bytes: Vec<u8>,
+    pub gain_db: f64,
+}
+
+/// Reads `input`, downmixes/resamples/trims/normalizes it per `cfg`, and
+/// returns the encoded WAV bytes together with the applied normalization
+/// gain as a [`ProcessResult`]. This is the entry point for batch or
+/// build-script use; `main` is a thin CLI wrapper around it.
+pub fn process_ir(input: &Path, cfg: &IrConfig) -> Result<ProcessResult> {
+    let (channels, src_sr) = read_channels(input)?;
+    let samples = downmix(&channels, &cfg.mix)?;
+    Ok(process_samples(&samples, src_sr, cfg))
+}
+
+/// Runs the resample/trim-and-fade/normalize/encode stages over an
+/// already-downmixed mono buffer, returning the encoded WAV bytes and the
+/// applied normalization gain as a [`ProcessResult`].
+pub fn process_samples(samples: &[f64], src_sr: u32, cfg: &IrConfig) -> ProcessResult {
+    let mut samples = if src_sr != cfg.sample_rate {
+        resample(samples, src_sr, cfg.sample_rate, cfg.interp)
+    } else {
+        samples.to_vec()
+    };
+
+    // Trim or pad to `cfg.length` samples, fading the real tail first so the
+    // cut (or the start of zero-padding) doesn't leave an audible click.
+    let real_len = samples.len();
+    let retained_len = real_len.min(cfg.length);
+    if real_len >= cfg.length {
+        samples.truncate(cfg.length);
+        apply_fade_tail(&mut samples, cfg.length, cfg.fade);
+    } else {
+        apply_fade_tail(&mut samples, real_len, cfg.fade);
+        samples.resize(cfg.length, 0.0);
+    }
+
+    let gain_db = normalize(&mut samples, retained_len, cfg.normalize);
+
+    ProcessResult {
+        bytes: build_wav_bytes(&samples, cfg.sample_rate, cfg.out_bits),
+        gain_db,
+    }
+}
+
+/// Applies a half-Hann fade-out over the last `fade` samples of
+/// `samples[..real_len]`, so the retained audio decays smoothly to zero
+/// instead of cutting or butting up against silence abruptly.
+fn apply_fade_tail(samples: &mut [f64], real_len: usize, fade: usize) {
+    let m = fade.min(real_len);
+    if m <= 1 {
+        return;
+    }
+    let start = real_len - m;
+    for k in 0..m {
+        let w = 0.5 * (1.0 + (PI * k as f64 / (m - 1) as f64).cos());
+        samples[start + k] *= w;
+    }
+}
+
+/// Scales `samples[..retained_len]` (and, incidentally, any trailing zero-pad)
+/// to hit `mode`'s target level. Returns the applied gain in dB.
+fn normalize(samples: &mut [f64], retained_len: usize, mode: NormalizeMode) -> f64 {
+    let retained = &samples[..retained_len.min(samples.len())];
+
+    let gain = match mode {
+        NormalizeMode::Off => 1.0,
+        NormalizeMode::Peak { target_dbfs } => {
+            let peak = retained.iter().fold(0.0_f64, |acc, &s| acc.max(s.abs()));
+            if peak == 0.0 {
+                1.0
+            } else {
+                db_to_linear(target_dbfs) / peak
+            }
+        }
+        NormalizeMode::Rms { target_dbfs } => {
+            let mean_sq = if retained.is_empty() {
+                0.0
+            } else {
+                retained.iter().map(|&s| s * s).sum::<f64>() / retained.len() as f64
+            };
+            let rms = mean_sq.sqrt();
+            if rms == 0.0 {
+                1.0
+            } else {
+                db_to_linear(target_dbfs) / rms
+            }
+        }
+    };
+
+    for s in samples.iter_mut() {
+        *s *= gain;
+    }
+
+    20.0 * gain.log10()
+}
+
+fn db_to_linear(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+/// Reads a PCM (8/16/24/32-bit int) or IEEE-float (32-bit) WAV, deinterleaved
+/// into one normalized `[-1.0, 1.0]` buffer per channel, along with the
+/// file's native sample rate so the caller can resample if needed.
+fn read_channels(path: &Path) -> Result<(Vec<Vec<f64>>, u32)> {
+    let mut reader = WavReader::open(path).with_context(|| format!("open {:?}", path))?;
+    let spec = reader.spec();
+    let num_channels = spec.channels as usize;
+
+    let interleaved: Vec<f64> = match spec.sample_format {
+        SampleFormat::Int => {
+            if ![8, 16, 24, 32].contains(&spec.bits_per_sample) {
+                bail!("Expected 8/16/24/32-bit int PCM, got {}-bit", spec.bits_per_sample);
+            }
+            let full_scale = (1i64 << (spec.bits_per_sample - 1)) as f64;
+            let mut out = Vec::new();
+            for s in reader.samples::<i32>() {
+                out.push(s? as f64 / full_scale);
+            }
+            out
+        }
+        SampleFormat::Float => {
+            if spec.bits_per_sample != 32 {
+                bail!("Expected 32-bit float PCM, got {}-bit", spec.bits_per_sample);
+            }
+            let mut out = Vec::new();
+            for s in reader.samples::<f32>() {
+                out.push(s? as f64);
+            }
+            out
+        }
+    };
+
+    let mut channels = vec![Vec::with_capacity(interleaved.len() / num_channels.max(1)); num_channels];
+    for (i, s) in interleaved.into_iter().enumerate() {
+        channels[i % num_channels].push(s);
+    }
+
+    Ok((channels, spec.sample_rate))
+}
+
+/// Folds per-channel buffers down to a single mono buffer per `mode`.
+/// `mode` is validated (weight count, pick index) even for a single-channel
+/// input, so `--pick`/`--mix` still error on an out-of-range or mismatched
+/// request instead of silently ignoring them.
+fn downmix(channels: &[Vec<f64>], mode: &MixMode) -> Result<Vec<f64>> {
+    let num_channels = channels.len();
+
+    match mode {
+        MixMode::Average => {
+            let len = channels.iter().map(Vec::len).max().unwrap_or(0);
+            let mut out = vec![0.0; len];
+            for ch in channels {
+                for (i, &s) in ch.iter().enumerate() {
+                    out[i] += s / num_channels as f64;
+                }
+            }
+            Ok(out)
+        }
+        MixMode::Weights(weights) => {
+            if weights.len() != num_channels {
+                bail!(
+                    "--mix has {} weight(s) but input has {} channel(s)",
+                    weights.len(),
+                    num_channels
+                );
+            }
+            let len = channels.iter().map(Vec::len).max().unwrap_or(0);
+            let mut out = vec![0.0; len];
+            for (ch, &w) in channels.iter().zip(weights) {
+                for (i, &s) in ch.iter().enumerate() {
+                    out[i] += s * w;
+                }
+            }
+            Ok(out)
+        }
+        MixMode::Pick(k) => channels
+            .get(*k)
+            .cloned()
+            .with_context(|| format!("--pick {} out of range (input has {} channel(s))", k, num_channels)),
+    }
+}
+
+/// Resamples `src_sr`-rate normalized samples to `dst_sr` using the given kernel.
+/// Source indices are reflected at the edges so the kernel stays well-defined
+/// near the start/end of the buffer.
+fn resample(samples: &[f64], src_sr: u32, dst_sr: u32, mode: InterpMode) -> Vec<f64> {
+    if samples.is_empty() || src_sr == dst_sr {
+        return samples.to_vec();
+    }
+
+    let src = samples;
+    let ratio = src_sr as f64 / dst_sr as f64;
+    let out_len = ((samples.len() as f64) / ratio).round().max(0.0) as usize;
+
+    let mut out = Vec::with_capacity(out_len);
+    for i in 0..out_len {
+        let x = i as f64 * ratio;
+        let n = x.floor() as isize;
+        let t = x - n as f64;
+        let y = match mode {
+            InterpMode::Nearest => at(src, (x.round()) as isize),
+            InterpMode::Linear => {
+                let s0 = at(src, n);
+                let s1 = at(src, n + 1);
+                s0 * (1.0 - t) + s1 * t
+            }
+            InterpMode::Cosine => {
+                let s0 = at(src, n);
+                let s1 = at(src, n + 1);
+                let m = (1.0 - (t * PI).cos()) / 2.0;
+                s0 * (1.0 - m) + s1 * m
+            }
+            InterpMode::Cubic => {
+                let p0 = at(src, n - 1);
+                let p1 = at(src, n);
+                let p2 = at(src, n + 1);
+                let p3 = at(src, n + 2);
+                p1 + 0.5
+                    * t
+                    * ((p2 - p0)
+                        + t * ((2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3)
+                            + t * (3.0 * (p1 - p2) + p3 - p0)))
+            }
+            InterpMode::Polyphase { radius } => {
+                let r = radius as isize;
+                let mut acc = 0.0;
+                for k in -r..=r {
+                    acc += at(src, n + k) * sinc_window(k as f64 - t, radius as f64);
+                }
+                acc
+            }
+        };
+        out.push(y);
+    }
+    out
+}
+
+/// Reflects `idx` into `[0, src.len())` and returns the sample there (zero for
+/// an empty buffer).
+fn at(src: &[f64], idx: isize) -> f64 {
+    if src.is_empty() {
+        return 0.0;
+    }
+    let len = src.len() as isize;
+    let mut i = idx;
+    if i < 0 {
+        i = -i;
+    }
+    if i >= len {
+        i = 2 * (len - 1) - i;
+    }
+    let i = i.clamp(0, len - 1) as usize;
+    src[i]
+}
+
+/// Hann-windowed sinc tap at offset `x` over a `±radius` window.
+fn sinc_window(x: f64, radius: f64) -> f64 {
+    if x.abs() >= radius {
+        return 0.0;
+    }
+    let s = if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    };
+    let w = 0.5 * (1.0 + (PI * x / radius).cos());
+    s * w
+}
+
+/// Builds an in-memory classic WAV (no extensible, no metadata):
+/// RIFF + fmt(16) + data, mono, `sample_rate` Hz, encoded at `out_bits`.
+/// PCM tag=1 for integer depths, tag=3 (IEEE float) for `OutBits::Float32`.
+fn build_wav_bytes(samples: &[f64], sample_rate: u32, out_bits: OutBits) -> Vec<u8> {
+    let bytes_per_sample: u32 = match out_bits {
+        OutBits::Int16 => 2,
+        OutBits::Int24 => 3,
+        OutBits::Float32 => 4,
+    };
+    let data_bytes = samples.len() as u32 * bytes_per_sample;
+
+    // RIFF size = 4("WAVE") + (8+16 fmt) + (8+data)
+    let riff_size = 4u32 + (8 + 16) + (8 + data_bytes);
+
+    let mut buf = Vec::with_capacity(44 + data_bytes as usize);
+
+    // RIFF header
+    buf.extend_from_slice(b"RIFF");
+    buf.extend_from_slice(&riff_size.to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+
+    // fmt chunk
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&16u32.to_le_bytes()); // fmt size
+
+    let audio_format: u16 = match out_bits {
+        OutBits::Float32 => 3,
+        OutBits::Int16 | OutBits::Int24 => 1,
+    };
+    let num_channels: u16 = 1;
+    let bits_per_sample: u16 = (bytes_per_sample * 8) as u16;
+    let block_align: u16 = num_channels * bytes_per_sample as u16;
+    let byte_rate: u32 = sample_rate * block_align as u32;
+
+    buf.extend_from_slice(&audio_format.to_le_bytes());
+    buf.extend_from_slice(&num_channels.to_le_bytes());
+    buf.extend_from_slice(&sample_rate.to_le_bytes());
+    buf.extend_from_slice(&byte_rate.to_le_bytes());
+    buf.extend_from_slice(&block_align.to_le_bytes());
+    buf.extend_from_slice(&bits_per_sample.to_le_bytes());
+
+    // data chunk
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&data_bytes.to_le_bytes());
+
+    for &s in samples {
+        match out_bits {
+            OutBits::Int16 => {
+                let v = (s * 32_768.0).round().clamp(-32_768.0, 32_767.0) as i16;
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            OutBits::Int24 => {
+                let v = (s * 8_388_608.0).round().clamp(-8_388_608.0, 8_388_607.0) as i32;
+                let u = v as u32;
+                buf.push((u & 0xFF) as u8);
+                buf.push(((u >> 8) & 0xFF) as u8);
+                buf.push(((u >> 16) & 0xFF) as u8);
+            }
+            OutBits::Float32 => {
+                buf.extend_from_slice(&(s as f32).to_le_bytes());
+            }
+        }
+    }
+
+    buf
+}
+
+/// Writes bytes atomically to `path`:
+/// - temp file in same dir
+/// - single buffered write
+/// - flush + fsync
+/// - rename over target
+pub fn write_atomic_synced(path: &Path, bytes: &[u8]) -> Result<()> {
+    let (tmp_path, parent) = temp_path_in_same_dir(path);
+
+    // Create temp file with restrictive perms.
+    {
+        let mut f = File::create(&tmp_path)
+            .with_context(|| format!("create temp file {:?}", tmp_path))?;
+        f.write_all(bytes)
+            .with_context(|| format!("write temp file {:?}", tmp_path))?;
+        f.flush().with_context(|| "flush temp file".to_string())?;
+        f.sync_all().with_context(|| "fsync temp file".to_string())?;
+    }
+
+    // Atomic rename within same filesystem.
+    rename(&tmp_path, path).with_context(|| {
+        format!(
+            "rename temp {:?} -> {:?} (must be same filesystem)",
+            tmp_path, path
+        )
+    })?;
+
+    // Best-effort sync the directory entry (helps on removable media)
+    if let Some(dir) = parent {
+        let _ = sync_dir(&dir);
+    }
+
+    Ok(())
+}
+
+fn temp_path_in_same_dir(path: &Path) -> (PathBuf, Option<PathBuf>) {
+    let parent = path.parent().map(|p| p.to_path_buf());
+    let stem = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "output.wav".to_string());
+
+    let tmp_name = format!(".{}.tmp", stem);
+    let tmp_path = match &parent {
+        Some(p) => p.join(tmp_name),
+        None => PathBuf::from(tmp_name),
+    };
+    (tmp_path, parent)
+}
+
+pub fn sync_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        sync_dir(parent)?;
+    }
+    Ok(())
+}
+
+fn sync_dir(dir: &Path) -> Result<()> {
+    // On macOS/Linux, opening a directory and fsyncing it helps ensure rename is persisted.
+    // This may fail on some filesystems; caller can ignore.
+    let d = OpenOptions::new().read(true).open(dir)?;
+    d.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f64 = 1e-9;
+
+    #[test]
+    fn resample_output_length_matches_ratio() {
+        let samples = vec![0.0; 1000];
+        let out = resample(&samples, 44_100, 48_000, InterpMode::Linear);
+        let expected = (1000.0 * 48_000.0 / 44_100.0_f64).round() as usize;
+        assert_eq!(out.len(), expected);
+    }
+
+    #[test]
+    fn resample_nearest_known_values() {
+        let samples = vec![0.0, 10.0];
+        let out = resample(&samples, 2, 4, InterpMode::Nearest);
+        assert_eq!(out, vec![0.0, 10.0, 10.0, 0.0]);
+    }
+
+    #[test]
+    fn resample_linear_known_values() {
+        let samples = vec![0.0, 10.0];
+        let out = resample(&samples, 2, 4, InterpMode::Linear);
+        assert_eq!(out, vec![0.0, 5.0, 10.0, 5.0]);
+    }
+
+    #[test]
+    fn resample_cosine_known_values() {
+        let samples = vec![0.0, 10.0];
+        let out = resample(&samples, 2, 4, InterpMode::Cosine);
+        let expected = [0.0, 5.0, 10.0, 5.0];
+        for (got, want) in out.iter().zip(expected) {
+            assert!((got - want).abs() < EPS, "got {got}, want {want}");
+        }
+    }
+
+    #[test]
+    fn resample_cubic_known_value_at_midpoint() {
+        let samples = vec![0.0, 10.0];
+        let out = resample(&samples, 2, 4, InterpMode::Cubic);
+        assert!((out[1] - 5.0).abs() < EPS);
+    }
+
+    #[test]
+    fn resample_polyphase_preserves_dc() {
+        let samples = vec![3.0; 32];
+        let out = resample(&samples, 2, 3, InterpMode::Polyphase { radius: 8 });
+        for &s in &out[8..out.len() - 8] {
+            assert!((s - 3.0).abs() < 1e-2, "expected DC-preserving value near 3.0, got {s}");
+        }
+    }
+
+    #[test]
+    fn build_wav_bytes_int16_header_and_sample() {
+        let bytes = build_wav_bytes(&[0.5], 48_000, OutBits::Int16);
+        assert_eq!(u16::from_le_bytes([bytes[20], bytes[21]]), 1); // PCM tag
+        assert_eq!(u16::from_le_bytes([bytes[34], bytes[35]]), 16); // bits_per_sample
+        let sample = i16::from_le_bytes([bytes[44], bytes[45]]);
+        assert_eq!(sample, (0.5_f64 * 32_768.0).round() as i16);
+    }
+
+    #[test]
+    fn build_wav_bytes_float32_header_and_sample() {
+        let bytes = build_wav_bytes(&[0.5], 48_000, OutBits::Float32);
+        assert_eq!(u16::from_le_bytes([bytes[20], bytes[21]]), 3); // IEEE float tag
+        assert_eq!(u16::from_le_bytes([bytes[34], bytes[35]]), 32); // bits_per_sample
+        let sample = f32::from_le_bytes([bytes[44], bytes[45], bytes[46], bytes[47]]);
+        assert_eq!(sample, 0.5_f32);
+    }
+
+    #[test]
+    fn downmix_validates_pick_even_for_mono_input() {
+        let channels = vec![vec![1.0, 2.0]];
+        let err = downmix(&channels, &MixMode::Pick(5)).unwrap_err();
+        assert!(err.to_string().contains("out of range"));
+    }
+
+    #[test]
+    fn downmix_validates_mix_weight_count_even_for_mono_input() {
+        let channels = vec![vec![1.0, 2.0]];
+        let err = downmix(&channels, &MixMode::Weights(vec![0.5, 0.5])).unwrap_err();
+        assert!(err.to_string().contains("weight"));
+    }
+
+    #[test]
+    fn downmix_average_mixes_stereo_channels() {
+        let channels = vec![vec![1.0, -1.0, 0.5], vec![0.2, 0.4, -0.5]];
+        let out = downmix(&channels, &MixMode::Average).unwrap();
+        let expected = [0.6, -0.3, 0.0];
+        for (got, want) in out.iter().zip(expected) {
+            assert!((got - want).abs() < EPS, "got {got}, want {want}");
+        }
+    }
+
+    #[test]
+    fn downmix_weights_applies_per_channel_mix() {
+        let channels = vec![vec![1.0, -1.0, 0.5], vec![0.2, 0.4, -0.5]];
+        let out = downmix(&channels, &MixMode::Weights(vec![0.7, 0.3])).unwrap();
+        let expected = [
+            1.0 * 0.7 + 0.2 * 0.3,
+            -0.7 + 0.4 * 0.3,
+            0.5 * 0.7 + -0.5 * 0.3,
+        ];
+        for (got, want) in out.iter().zip(expected) {
+            assert!((got - want).abs() < EPS, "got {got}, want {want}");
+        }
+    }
+
+    #[test]
+    fn fade_tail_ramps_from_one_to_zero() {
+        let mut samples = vec![1.0; 10];
+        apply_fade_tail(&mut samples, 10, 4);
+        assert!((samples[6] - 1.0).abs() < EPS, "fade start should be unattenuated");
+        assert!(samples[9].abs() < EPS, "last retained sample should fade to zero");
+    }
+
+    #[test]
+    fn fade_tail_only_touches_the_window() {
+        let mut samples = vec![1.0; 10];
+        apply_fade_tail(&mut samples, 10, 4);
+        for &s in &samples[..6] {
+            assert!((s - 1.0).abs() < EPS, "samples before the fade window must be untouched");
+        }
+    }
+
+    #[test]
+    fn normalize_peak_hits_target_dbfs() {
+        let mut samples = vec![0.1, -0.5, 0.3];
+        let target_dbfs = -0.1;
+        let len = samples.len();
+        normalize(&mut samples, len, NormalizeMode::Peak { target_dbfs });
+        let peak = samples.iter().fold(0.0_f64, |acc, &s| acc.max(s.abs()));
+        assert!((peak - db_to_linear(target_dbfs)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normalize_rms_hits_target_dbfs() {
+        let mut samples = vec![0.2, -0.3, 0.1, -0.05];
+        let target_dbfs = -18.0;
+        let len = samples.len();
+        normalize(&mut samples, len, NormalizeMode::Rms { target_dbfs });
+        let rms = (samples.iter().map(|&s| s * s).sum::<f64>() / samples.len() as f64).sqrt();
+        assert!((rms - db_to_linear(target_dbfs)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normalize_off_leaves_samples_and_reports_zero_gain() {
+        let mut samples = vec![0.2, -0.3];
+        let len = samples.len();
+        let gain_db = normalize(&mut samples, len, NormalizeMode::Off);
+        assert_eq!(samples, vec![0.2, -0.3]);
+        assert!((gain_db - 0.0).abs() < EPS);
+    }
+
+    fn write_mono_wav(name: &str, bits: u16, sample_format: SampleFormat, sample_rate: u32, value: f64) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fryette_ir_test_{name}.wav"));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: bits,
+            sample_format,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        match sample_format {
+            SampleFormat::Int => {
+                let full_scale = (1i64 << (bits - 1)) as f64;
+                writer.write_sample((value * full_scale).round() as i32).unwrap();
+            }
+            SampleFormat::Float => {
+                writer.write_sample(value as f32).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    #[test]
+    fn read_channels_normalizes_8bit_int() {
+        let path = write_mono_wav("read_8bit", 8, SampleFormat::Int, 44_100, 0.5);
+        let (channels, sr) = read_channels(&path).unwrap();
+        assert_eq!(sr, 44_100);
+        assert_eq!(channels.len(), 1);
+        assert!((channels[0][0] - 0.5).abs() < 1.0 / 128.0);
+    }
+
+    #[test]
+    fn read_channels_normalizes_16bit_int() {
+        let path = write_mono_wav("read_16bit", 16, SampleFormat::Int, 48_000, 0.5);
+        let (channels, sr) = read_channels(&path).unwrap();
+        assert_eq!(sr, 48_000);
+        assert!((channels[0][0] - 0.5).abs() < EPS);
+    }
+
+    #[test]
+    fn read_channels_normalizes_24bit_int() {
+        let path = write_mono_wav("read_24bit", 24, SampleFormat::Int, 48_000, 0.5);
+        let (channels, sr) = read_channels(&path).unwrap();
+        assert_eq!(sr, 48_000);
+        assert!((channels[0][0] - 0.5).abs() < EPS);
+    }
+
+    #[test]
+    fn read_channels_normalizes_32bit_int() {
+        let path = write_mono_wav("read_32bit", 32, SampleFormat::Int, 48_000, 0.5);
+        let (channels, sr) = read_channels(&path).unwrap();
+        assert_eq!(sr, 48_000);
+        assert!((channels[0][0] - 0.5).abs() < EPS);
+    }
+
+    #[test]
+    fn read_channels_normalizes_32bit_float() {
+        let path = write_mono_wav("read_32float", 32, SampleFormat::Float, 96_000, 0.5);
+        let (channels, sr) = read_channels(&path).unwrap();
+        assert_eq!(sr, 96_000);
+        assert!((channels[0][0] - 0.5).abs() < EPS);
+    }
+}